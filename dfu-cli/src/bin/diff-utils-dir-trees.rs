@@ -35,18 +35,71 @@ use std::fmt;
 use std::io::{self, Write};
 use std::path::Path;
 
-use dfu_core::directories::{self, TreeDiff};
+use dfu_core::directories::{self, AlwaysMatch, DiffConfig, TreeDiff};
+use dfu_core::files::EqualityMode;
 
 fn main() {
     let args = App::new("diff-utils-dir-trees")
         .arg(Arg::with_name("dir1").required(true).takes_value(true))
         .arg(Arg::with_name("dir2").required(true).takes_value(true))
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Glob pattern of paths to exclude from the scan; may be repeated"),
+        )
+        .arg(
+            Arg::with_name("include")
+                .long("include")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Glob pattern re-admitting paths an --exclude or .gitignore would drop; may be repeated"),
+        )
+        .arg(
+            Arg::with_name("respect-gitignore")
+                .long("respect-gitignore")
+                .help("Skip paths matched by .gitignore/.ignore files found under each directory"),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .long("threads")
+                .takes_value(true)
+                .help("Compare entries using N worker threads instead of scanning sequentially"),
+        )
+        .arg(
+            Arg::with_name("equality-mode")
+                .long("equality-mode")
+                .takes_value(true)
+                .possible_values(&["byte-exact", "size-and-mtime", "size-only"])
+                .default_value("byte-exact")
+                .help("How much trust to place in file size/mtime before reading file contents"),
+        )
         .get_matches();
     let (path1, path2) = (
         Path::new(args.value_of("dir1").unwrap()),
         Path::new(args.value_of("dir2").unwrap()),
     );
 
+    let exclude: Vec<String> = args
+        .values_of("exclude")
+        .map(|v| v.map(String::from).collect())
+        .unwrap_or_default();
+    let include: Vec<String> = args
+        .values_of("include")
+        .map(|v| v.map(String::from).collect())
+        .unwrap_or_default();
+
+    let mut config = DiffConfig::new();
+    if args.is_present("respect-gitignore") {
+        config = config.with_gitignore(&[path1, path2]);
+    }
+    let config = config
+        .with_patterns(&[path1, path2], &exclude, &include)
+        .expect("invalid --exclude/--include pattern");
+
     // For pretty table formatting, we need to know the name of the longest identifier.
     let col_width = std::cmp::max(
         directories::longest_identifier(path1).expect("failed to access dir1"),
@@ -59,7 +112,41 @@ fn main() {
         path2.display()
     );
 
-    for res in directories::diff_trees(&path1, &path2, 1) {
+    let threads: Option<usize> = args
+        .value_of("threads")
+        .map(|n| n.parse().expect("--threads must be a positive integer"));
+
+    let equality_mode = match args.value_of("equality-mode").unwrap() {
+        "byte-exact" => EqualityMode::ByteExact,
+        "size-and-mtime" => EqualityMode::SizeAndMtime,
+        "size-only" => EqualityMode::SizeOnly,
+        _ => unreachable!("restricted by possible_values"),
+    };
+
+    let results: Box<dyn Iterator<Item = _>> = match threads {
+        Some(num_threads) => Box::new(
+            directories::diff_trees_parallel(
+                &path1,
+                &path2,
+                1,
+                equality_mode,
+                config,
+                &AlwaysMatch,
+                num_threads,
+            )
+            .into_iter(),
+        ),
+        None => Box::new(directories::diff_trees(
+            &path1,
+            &path2,
+            1,
+            equality_mode,
+            config,
+            &AlwaysMatch,
+        )),
+    };
+
+    for res in results {
         match res {
             Ok(td) => println!("{}", display_tree_diff(&td, col_width)),
             // Once an error is encountered, scanning must be stopped to ensure accurate results.
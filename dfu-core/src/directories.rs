@@ -1,17 +1,196 @@
 use std::{
     cmp::Ordering,
+    collections::VecDeque,
     io,
     iter::Peekable,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+};
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    overrides::{Override, OverrideBuilder},
 };
 use walkdir::{DirEntry, WalkDir};
 
+use crate::files::EqualityMode;
+
 #[derive(Error, Debug)]
 pub enum DirError {
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
     #[error("failed while walking directories: {0}")]
     Walk(#[from] walkdir::Error),
+    #[error("invalid include/exclude pattern: {0}")]
+    Pattern(#[from] ignore::Error),
+}
+
+/// Compiled filtering rules applied uniformly to both sides of a tree comparison.
+///
+/// Built once and shared by both walkers in [`diff_trees`]/[`diff_dirs`], so that a file ignored
+/// on one side but not scanned on the other never gets mistaken for a [`TreeDiff::Left`] or
+/// [`TreeDiff::Right`] difference.
+#[derive(Clone)]
+pub struct DiffConfig {
+    gitignores: Vec<(PathBuf, Gitignore)>,
+    patterns: Vec<(PathBuf, Gitignore)>,
+    #[cfg(unix)]
+    inode_short_circuit: bool,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        DiffConfig {
+            gitignores: Vec::new(),
+            patterns: Vec::new(),
+            #[cfg(unix)]
+            inode_short_circuit: true,
+        }
+    }
+}
+
+impl DiffConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disables the `(dev, ino)` short-circuit that treats two same-named files sharing a
+    /// device and inode (hard links, or reflinks on filesystems that expose them that way) as
+    /// equal without reading either one. Enabled by default; disable when verifying that two
+    /// trees are genuinely independent copies rather than linked to the same data.
+    #[cfg(unix)]
+    pub fn without_inode_short_circuit(mut self) -> Self {
+        self.inode_short_circuit = false;
+        self
+    }
+
+    /// Loads every `.gitignore`/`.ignore` file found under each of `roots` and applies them for
+    /// the remainder of the scan, matching the precedence rules of `git` and `ripgrep`. Each
+    /// root's rules are only ever checked against entries from that same root, since
+    /// `Gitignore` panics if asked to match a path outside the root it was built from. A rule
+    /// found under only one root therefore does not apply to the other root, and a path it
+    /// ignores on that side alone can still be reported as `Left`/`Right`.
+    pub fn with_gitignore<P: AsRef<Path>>(mut self, roots: &[P]) -> Self {
+        self.gitignores = roots
+            .iter()
+            .map(|root| (root.as_ref().to_path_buf(), build_gitignore(root.as_ref())))
+            .collect();
+        self
+    }
+
+    /// Compiles explicit exclude/include glob patterns, once per root in `roots`, so the same
+    /// patterns apply uniformly to both sides of the comparison. An include pattern re-admits
+    /// paths that an exclude pattern (or `.gitignore`) would otherwise drop.
+    pub fn with_patterns<P: AsRef<Path>>(
+        mut self,
+        roots: &[P],
+        exclude: &[String],
+        include: &[String],
+    ) -> Result<Self, DirError> {
+        if exclude.is_empty() && include.is_empty() {
+            return Ok(self);
+        }
+
+        let mut patterns = Vec::with_capacity(roots.len());
+        for root in roots {
+            let mut builder = GitignoreBuilder::new(root);
+            for pattern in exclude {
+                builder.add_line(None, pattern)?;
+            }
+            for pattern in include {
+                builder.add_line(None, &format!("!{}", pattern))?;
+            }
+            patterns.push((root.as_ref().to_path_buf(), builder.build()?));
+        }
+        self.patterns = patterns;
+        Ok(self)
+    }
+
+    /// Returns `true` if `entry` should be skipped before it reaches the comparison logic.
+    fn is_excluded(&self, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type().is_dir();
+
+        for (root, patterns) in &self.patterns {
+            if !entry.path().starts_with(root) {
+                continue;
+            }
+            let matched = patterns.matched_path_or_any_parents(entry.path(), is_dir);
+            if matched.is_whitelist() {
+                return false;
+            }
+            if matched.is_ignore() {
+                return true;
+            }
+        }
+
+        for (root, gitignore) in &self.gitignores {
+            if !entry.path().starts_with(root) {
+                continue;
+            }
+            if gitignore
+                .matched_path_or_any_parents(entry.path(), is_dir)
+                .is_ignore()
+            {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Decides whether an entry participates in a tree comparison at all. Matching is fallible; a
+/// matcher error surfaces as an `Err` item in the comparison stream, just like a walk error.
+pub trait Matcher: Send + Sync {
+    fn matches(&self, path: &Path) -> Result<bool, DirError>;
+}
+
+/// A [`Matcher`] that admits every path. The default when a caller has no matching rules of
+/// their own.
+pub struct AlwaysMatch;
+
+impl Matcher for AlwaysMatch {
+    fn matches(&self, _path: &Path) -> Result<bool, DirError> {
+        Ok(true)
+    }
+}
+
+/// A [`Matcher`] backed by a fixed set of glob patterns, anchored to a root directory.
+pub struct GlobMatcher {
+    overrides: Override,
+}
+
+impl GlobMatcher {
+    pub fn new<P: AsRef<Path>>(root: P, patterns: &[String]) -> Result<Self, DirError> {
+        let mut builder = OverrideBuilder::new(root);
+        for pattern in patterns {
+            builder.add(pattern)?;
+        }
+        Ok(GlobMatcher {
+            overrides: builder.build()?,
+        })
+    }
+}
+
+impl Matcher for GlobMatcher {
+    fn matches(&self, path: &Path) -> Result<bool, DirError> {
+        Ok(!self.overrides.matched(path, path.is_dir()).is_ignore())
+    }
+}
+
+fn build_gitignore(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name() == ".gitignore" || e.file_name() == ".ignore")
+    {
+        // A single malformed ignore file shouldn't abort the whole scan; skip it.
+        let _ = builder.add(entry.path());
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
 }
 
 /// Finds the length of the longest identifier (including `path`) in `path`'s descendants.
@@ -32,25 +211,63 @@ pub enum TreeDiff {
     Differs(PathBuf, PathBuf),
 }
 
-pub fn diff_trees<P1: AsRef<Path>, P2: AsRef<Path>>(
+pub fn diff_trees<'m, P1: AsRef<Path>, P2: AsRef<Path>>(
     dir1: &P1,
     dir2: &P2,
     depth: usize,
-) -> TreeDiffIter<impl Iterator<Item=Result<DirEntry, walkdir::Error>>> {
+    mode: EqualityMode,
+    config: DiffConfig,
+    matcher: &'m dyn Matcher,
+) -> TreeDiffIter<'m, impl Iterator<Item=Result<DirEntry, walkdir::Error>>> {
     let walker1 = WalkDir::new(dir1)
         .min_depth(depth)
         .max_depth(depth)
         .sort_by(file_name_cmp)
         .into_iter()
         .peekable();
-    let walker2 = WalkDir::new(dir1)
+    let walker2 = WalkDir::new(dir2)
         .min_depth(depth)
         .max_depth(depth)
         .sort_by(file_name_cmp)
         .into_iter()
         .peekable();
 
-    TreeDiffIter { walker1, walker2 }
+    TreeDiffIter { walker1, walker2, mode, config, matcher }
+}
+
+/// Advances `walker` past any leading entries `config` or `matcher` reject, so that both sides
+/// of a comparison only ever see admitted entries. Returns the matcher's error, if any, without
+/// consuming entries beyond the one that produced it.
+fn skip_excluded<I>(
+    walker: &mut Peekable<I>,
+    config: &DiffConfig,
+    matcher: &dyn Matcher,
+) -> Option<DirError>
+where
+    I: Iterator<Item = Result<DirEntry, walkdir::Error>>,
+{
+    loop {
+        let entry = match walker.peek() {
+            Some(Ok(entry)) => entry,
+            _ => return None,
+        };
+
+        if config.is_excluded(entry) {
+            walker.next();
+            continue;
+        }
+
+        match matcher.matches(entry.path()) {
+            Ok(true) => return None,
+            Ok(false) => {
+                walker.next();
+            }
+            Err(e) => {
+                walker.next();
+                return Some(e);
+            }
+        }
+    }
 }
 
 // # Warning: Error handling
@@ -60,17 +277,27 @@ pub fn diff_trees<P1: AsRef<Path>, P2: AsRef<Path>>(
 // indicating that `dir1/a` cannot be accessed, followed by a record indicating that `dir2/a` only
 // exists in `dir2` (despite the fact that it may exist in `dir1`, but we don't have permission to
 // access it).
-pub struct TreeDiffIter<I: Iterator> {
+pub struct TreeDiffIter<'m, I: Iterator> {
     walker1: Peekable<I>,
     walker2: Peekable<I>,
+    mode: EqualityMode,
+    config: DiffConfig,
+    matcher: &'m dyn Matcher,
 }
-impl<I> Iterator for TreeDiffIter<I>
+impl<'m, I> Iterator for TreeDiffIter<'m, I>
 where
     I: Iterator<Item = Result<DirEntry, walkdir::Error>>,
 {
     type Item = Result<TreeDiff, DirError>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = skip_excluded(&mut self.walker1, &self.config, self.matcher) {
+            return Some(Err(e));
+        }
+        if let Some(e) = skip_excluded(&mut self.walker2, &self.config, self.matcher) {
+            return Some(Err(e));
+        }
+
         match (self.walker1.peek(), self.walker2.peek()) {
             // Both iterators yield entries. Because the iterators are sorted, we can first check
             // for missing entries (i.e., names are not equal). If the names are equal, then we
@@ -78,7 +305,7 @@ where
             (Some(Ok(de1)), Some(Ok(de2))) => {
                 match file_name_cmp(de1, de2) {
                     // Names are equal. We need to scan the contents of both directories.
-                    Ordering::Equal => match diff_dirs(de1.path(), de2.path()) {
+                    Ordering::Equal => match diff_dirs(de1.path(), de2.path(), self.mode, &self.config, self.matcher) {
                         Ok(DiffResult::Equal) => {
                             Some(Ok(TreeDiff::Matches(self.walker1.next().unwrap().unwrap().into_path(), self.walker2.next().unwrap().unwrap().into_path())))
                         },
@@ -88,7 +315,7 @@ where
                         Err(e) => Some(Err(e))
                     }
                     // `de1` is later than `de2`. This means that `de2` was not found in `dir1`.
-                    Ordering::Greater => Some(Ok(TreeDiff::Left(
+                    Ordering::Greater => Some(Ok(TreeDiff::Right(
                         self.walker2.next().unwrap().unwrap().into_path(),
                     ))),
                     // `de2` is later than `de1`. This means that `de1` was not found in `dir2`.
@@ -103,7 +330,7 @@ where
             (Some(Ok(_de1)), None) => Some(Ok(TreeDiff::Left(
                 self.walker1.next().unwrap().unwrap().into_path(),
             ))),
-            (None, Some(Ok(_de2))) => Some(Ok(TreeDiff::Left(
+            (None, Some(Ok(_de2))) => Some(Ok(TreeDiff::Right(
                 self.walker2.next().unwrap().unwrap().into_path(),
             ))),
 
@@ -127,7 +354,13 @@ pub enum DiffResult {
     NotEqual,
 }
 
-pub fn diff_dirs<P1: AsRef<Path>, P2: AsRef<Path>>(dir1: P1, dir2: P2) -> Result<DiffResult, DirError> {
+pub fn diff_dirs<P1: AsRef<Path>, P2: AsRef<Path>>(
+    dir1: P1,
+    dir2: P2,
+    mode: EqualityMode,
+    config: &DiffConfig,
+    matcher: &dyn Matcher,
+) -> Result<DiffResult, DirError> {
     let mut walker1 = WalkDir::new(dir1)
         .sort_by(file_name_cmp)
         .into_iter()
@@ -136,8 +369,15 @@ pub fn diff_dirs<P1: AsRef<Path>, P2: AsRef<Path>>(dir1: P1, dir2: P2) -> Result
         .sort_by(file_name_cmp)
         .into_iter()
         .peekable();
-    
+
     loop {
+        if let Some(e) = skip_excluded(&mut walker1, config, matcher) {
+            return Err(e);
+        }
+        if let Some(e) = skip_excluded(&mut walker2, config, matcher) {
+            return Err(e);
+        }
+
         match (walker1.peek(), walker2.peek()) {
             // Both iterators yield entries. Because the iterators are sorted, we can first check
             // for missing entries (i.e., names are not equal). If the names are equal, then we
@@ -149,7 +389,17 @@ pub fn diff_dirs<P1: AsRef<Path>, P2: AsRef<Path>>(dir1: P1, dir2: P2) -> Result
                         if de1.file_type() != de2.file_type() {
                             return Ok(DiffResult::NotEqual);
                         }
-                        if de1.file_type().is_file() && !crate::files::file_contents_equal(de1.path(), de2.path())? {
+
+                        #[cfg(unix)]
+                        let already_equal =
+                            config.inode_short_circuit && same_inode(de1, de2)?;
+                        #[cfg(not(unix))]
+                        let already_equal = false;
+
+                        if de1.file_type().is_file()
+                            && !already_equal
+                            && !crate::files::file_contents_equal_cached(de1.path(), de2.path(), mode)?
+                        {
                             return Ok(DiffResult::NotEqual);
                         }
                         walker1.next();
@@ -185,14 +435,316 @@ fn file_name_cmp(a: &DirEntry, b: &DirEntry) -> Ordering {
     a.file_name().cmp(&b.file_name())
 }
 
+/// Returns `true` if `de1` and `de2` share a device and inode number, meaning they are the same
+/// physical file (e.g. hard-linked via `rsync --link-dest`) and can be treated as equal
+/// without reading either one.
+#[cfg(unix)]
+fn same_inode(de1: &DirEntry, de2: &DirEntry) -> Result<bool, DirError> {
+    use std::os::unix::fs::MetadataExt;
+    use walkdir::DirEntryExt;
+
+    if de1.ino() != de2.ino() {
+        return Ok(false);
+    }
+    Ok(de1.metadata()?.dev() == de2.metadata()?.dev())
+}
+
+/// A depth-`depth` entry matched up between `dir1` and `dir2`, before its contents have been
+/// compared. Produced by the same sequential walk-and-match logic as [`TreeDiffIter`], but
+/// stopping short of calling [`diff_dirs`] so the comparison itself can be parallelized.
+enum MatchedEntry {
+    Pair(PathBuf, PathBuf),
+    LeftOnly(PathBuf),
+    RightOnly(PathBuf),
+}
+
+/// Walks and matches entries exactly like [`TreeDiffIter`], but returns the matched pairs up
+/// front instead of comparing them inline. Listing top-level entries is cheap relative to the
+/// recursive content comparisons that follow, so this part stays sequential.
+fn collect_matched_entries<P1: AsRef<Path>, P2: AsRef<Path>>(
+    dir1: &P1,
+    dir2: &P2,
+    depth: usize,
+    config: &DiffConfig,
+    matcher: &dyn Matcher,
+) -> Vec<Result<MatchedEntry, DirError>> {
+    let mut walker1 = WalkDir::new(dir1)
+        .min_depth(depth)
+        .max_depth(depth)
+        .sort_by(file_name_cmp)
+        .into_iter()
+        .peekable();
+    let mut walker2 = WalkDir::new(dir2)
+        .min_depth(depth)
+        .max_depth(depth)
+        .sort_by(file_name_cmp)
+        .into_iter()
+        .peekable();
+
+    let mut out = Vec::new();
+    loop {
+        if let Some(e) = skip_excluded(&mut walker1, config, matcher) {
+            out.push(Err(e));
+            break;
+        }
+        if let Some(e) = skip_excluded(&mut walker2, config, matcher) {
+            out.push(Err(e));
+            break;
+        }
+
+        match (walker1.peek(), walker2.peek()) {
+            (Some(Ok(de1)), Some(Ok(de2))) => match file_name_cmp(de1, de2) {
+                Ordering::Equal => {
+                    let p1 = walker1.next().unwrap().unwrap().into_path();
+                    let p2 = walker2.next().unwrap().unwrap().into_path();
+                    out.push(Ok(MatchedEntry::Pair(p1, p2)));
+                }
+                Ordering::Greater => {
+                    out.push(Ok(MatchedEntry::RightOnly(
+                        walker2.next().unwrap().unwrap().into_path(),
+                    )));
+                }
+                Ordering::Less => {
+                    out.push(Ok(MatchedEntry::LeftOnly(
+                        walker1.next().unwrap().unwrap().into_path(),
+                    )));
+                }
+            },
+            (Some(Ok(_)), None) => {
+                out.push(Ok(MatchedEntry::LeftOnly(
+                    walker1.next().unwrap().unwrap().into_path(),
+                )));
+            }
+            (None, Some(Ok(_))) => {
+                out.push(Ok(MatchedEntry::RightOnly(
+                    walker2.next().unwrap().unwrap().into_path(),
+                )));
+            }
+            (Some(Err(_)), _) => {
+                out.push(Err(walker1.next().unwrap().unwrap_err().into()));
+            }
+            (_, Some(Err(_))) => {
+                out.push(Err(walker2.next().unwrap().unwrap_err().into()));
+            }
+            (None, None) => break,
+        }
+    }
+    out
+}
+
+/// Compares `dir1` and `dir2` at `depth` the same way [`diff_trees`] does, but farms the
+/// per-entry [`diff_dirs`] calls out to a bounded pool of `num_threads` worker threads.
+///
+/// Entries are matched up sequentially first (see [`collect_matched_entries`]); only the
+/// recursive content comparisons run in parallel, with results re-sorted back into original
+/// entry order before being returned.
+pub fn diff_trees_parallel<P1: AsRef<Path>, P2: AsRef<Path>>(
+    dir1: &P1,
+    dir2: &P2,
+    depth: usize,
+    mode: EqualityMode,
+    config: DiffConfig,
+    matcher: &dyn Matcher,
+    num_threads: usize,
+) -> Vec<Result<TreeDiff, DirError>> {
+    let entries = collect_matched_entries(dir1, dir2, depth, &config, matcher);
+
+    // Every slot starts unresolved; `LeftOnly`/`RightOnly` entries resolve immediately, `Pair`
+    // entries resolve once their worker thread completes (or not at all, if the scan aborted
+    // first).
+    let mut slots: Vec<Option<Result<TreeDiff, DirError>>> = Vec::with_capacity(entries.len());
+    let queue = VecDeque::new();
+    let queue = Arc::new(Mutex::new(queue));
+
+    for entry in entries {
+        let index = slots.len();
+        match entry {
+            Ok(MatchedEntry::LeftOnly(path)) => {
+                slots.push(Some(Ok(TreeDiff::Left(path))));
+            }
+            Ok(MatchedEntry::RightOnly(path)) => {
+                slots.push(Some(Ok(TreeDiff::Right(path))));
+            }
+            Ok(MatchedEntry::Pair(p1, p2)) => {
+                slots.push(None);
+                queue.lock().unwrap().push_back((index, p1, p2));
+            }
+            Err(e) => {
+                slots.push(Some(Err(e)));
+            }
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let abort = Arc::new(AtomicBool::new(false));
+    let active_workers = Arc::new(AtomicUsize::new(num_threads.max(1)));
+
+    // Scoped so worker closures can borrow `matcher` directly instead of requiring it to be
+    // `'static`; the scope blocks until every worker below has finished.
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads.max(1))
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                let abort = Arc::clone(&abort);
+                let active_workers = Arc::clone(&active_workers);
+                let config = config.clone();
+                let tx = tx.clone();
+
+                scope.spawn(move || {
+                    loop {
+                        if abort.load(AtomicOrdering::SeqCst) {
+                            break;
+                        }
+                        let task = queue.lock().unwrap().pop_front();
+                        match task {
+                            Some((index, p1, p2)) => {
+                                let result = diff_dirs(&p1, &p2, mode, &config, matcher).map(
+                                    |diff_result| match diff_result {
+                                        DiffResult::Equal => TreeDiff::Matches(p1, p2),
+                                        DiffResult::NotEqual => TreeDiff::Differs(p1, p2),
+                                    },
+                                );
+                                if result.is_err() {
+                                    abort.store(true, AtomicOrdering::SeqCst);
+                                }
+                                // The receiver always outlives every worker, so this can't fail.
+                                let _ = tx.send((index, result));
+                            }
+                            // The queue is empty: this worker has gone idle. Once every worker
+                            // reports idle, the pool is quiescent and there is nothing left to
+                            // do.
+                            None => break,
+                        }
+                    }
+                    active_workers.fetch_sub(1, AtomicOrdering::SeqCst);
+                })
+            })
+            .collect();
+        drop(tx);
+
+        for (index, result) in rx {
+            slots[index] = Some(result);
+        }
+        for handle in handles {
+            let _ = handle.join();
+        }
+    });
+    debug_assert_eq!(active_workers.load(AtomicOrdering::SeqCst), 0);
+
+    // Stop at the first error or first never-computed slot (left behind by an aborted scan), so
+    // the caller sees exactly the prefix that was validated successfully — the same invariant
+    // the sequential `TreeDiffIter` upholds.
+    let mut out = Vec::with_capacity(slots.len());
+    for slot in slots {
+        match slot {
+            Some(Ok(td)) => out.push(Ok(td)),
+            Some(Err(e)) => {
+                out.push(Err(e));
+                break;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn entry_for(path: &Path) -> DirEntry {
+        WalkDir::new(path)
+            .max_depth(0)
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn with_patterns_exclude_and_include() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("keep.log"), b"").unwrap();
+        std::fs::write(dir.path().join("drop.log"), b"").unwrap();
+        std::fs::write(dir.path().join("other.txt"), b"").unwrap();
+
+        let config = DiffConfig::new()
+            .with_patterns(
+                &[dir.path()],
+                &["*.log".to_string()],
+                &["keep.log".to_string()],
+            )
+            .unwrap();
+
+        assert!(!config.is_excluded(&entry_for(&dir.path().join("other.txt"))));
+        assert!(config.is_excluded(&entry_for(&dir.path().join("drop.log"))));
+        assert!(!config.is_excluded(&entry_for(&dir.path().join("keep.log"))));
+    }
+
+    #[test]
+    fn with_patterns_across_sibling_roots_does_not_panic() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        std::fs::write(dir1.path().join("a.log"), b"").unwrap();
+        std::fs::write(dir2.path().join("a.log"), b"").unwrap();
+
+        let config = DiffConfig::new()
+            .with_patterns(&[dir1.path(), dir2.path()], &["*.log".to_string()], &[])
+            .unwrap();
+
+        assert!(config.is_excluded(&entry_for(&dir1.path().join("a.log"))));
+        assert!(config.is_excluded(&entry_for(&dir2.path().join("a.log"))));
+    }
+
+    #[test]
+    fn with_gitignore_across_sibling_roots_does_not_panic() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        std::fs::write(dir1.path().join(".gitignore"), b"*.log\n").unwrap();
+        std::fs::write(dir1.path().join("a.log"), b"").unwrap();
+        std::fs::write(dir2.path().join("a.log"), b"").unwrap();
+
+        let config = DiffConfig::new().with_gitignore(&[dir1.path(), dir2.path()]);
+
+        assert!(config.is_excluded(&entry_for(&dir1.path().join("a.log"))));
+        assert!(!config.is_excluded(&entry_for(&dir2.path().join("a.log"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_inode_true_for_hard_linked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original");
+        let linked = dir.path().join("linked");
+        std::fs::write(&original, b"data").unwrap();
+        std::fs::hard_link(&original, &linked).unwrap();
+
+        assert!(same_inode(&entry_for(&original), &entry_for(&linked)).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_inode_false_for_independent_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::write(&a, b"data").unwrap();
+        std::fs::write(&b, b"data").unwrap();
+
+        assert!(!same_inode(&entry_for(&a), &entry_for(&b)).unwrap());
+    }
+
     #[test]
     fn diff_trees_sample() {
-        for res in diff_trees(&"./", &"./", 1) {
+        for res in diff_trees(
+            &"./",
+            &"./",
+            1,
+            crate::files::EqualityMode::ByteExact,
+            DiffConfig::new(),
+            &AlwaysMatch,
+        ) {
             match res {
                 Ok(td) => println!("{:?}", td),
                 // Once an error is encountered, scanning must be stopped to ensure accurate results.
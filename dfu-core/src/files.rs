@@ -1,47 +1,310 @@
 use std::{
-    fs::File,
+    fs::{File, Metadata},
     io::{self, Read},
     path::Path,
+    time::SystemTime,
 };
 
-pub fn file_contents_equal<P1: AsRef<Path>, P2: AsRef<Path>>(
+/// Selects how much trust [`file_contents_equal_cached`] places in filesystem metadata before
+/// falling back to reading file bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EqualityMode {
+    /// Always compare byte-for-byte, ignoring size and mtime. Equivalent to
+    /// [`file_contents_equal`].
+    ByteExact,
+    /// Trust matching size + a trustworthy, equal truncated mtime as proof of equality; fall
+    /// back to bytes otherwise. Safe for the common "backup vs. live" case where most files are
+    /// untouched copies.
+    SizeAndMtime,
+    /// Trust matching size alone. Fast, but wrong if a file's contents changed without its
+    /// length changing.
+    SizeOnly,
+}
+
+/// A modification time split into seconds and nanoseconds, plus whether it's safe to trust at
+/// all. Both components are kept at full resolution; the nanoseconds are never dropped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TruncatedMtime {
+    secs: i64,
+    nanos: u32,
+    trustworthy: bool,
+}
+
+fn truncated_mtime(metadata: &Metadata) -> io::Result<TruncatedMtime> {
+    let mtime = metadata.modified()?;
+    let since_epoch = mtime
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let secs = since_epoch.as_secs() as i64;
+    let nanos = since_epoch.subsec_nanos();
+
+    // A mtime that hasn't aged by at least a second can't be distinguished from "now", so it
+    // isn't trustworthy for equality.
+    let trustworthy = matches!(SystemTime::now().duration_since(mtime), Ok(age) if age.as_secs() >= 1);
+
+    Ok(TruncatedMtime { secs, nanos, trustworthy })
+}
+
+/// Like [`file_contents_equal`], but allowed to skip reading file bytes when `mode` permits
+/// trusting cheaper filesystem metadata instead.
+pub fn file_contents_equal_cached<P1: AsRef<Path>, P2: AsRef<Path>>(
     file1: P1,
     file2: P2,
+    mode: EqualityMode,
 ) -> Result<bool, io::Error> {
-    // If file lengths differ, the file contents differ.
-    if file1.as_ref().metadata()?.len() != file2.as_ref().metadata()?.len() {
+    let meta1 = file1.as_ref().metadata()?;
+    let meta2 = file2.as_ref().metadata()?;
+
+    if meta1.len() != meta2.len() {
         return Ok(false);
     }
 
-    let mut f1 = File::open(file1)?.bytes();
-    let mut f2 = File::open(file2)?.bytes();
+    match mode {
+        EqualityMode::SizeOnly => Ok(true),
+        EqualityMode::ByteExact => file_contents_equal(file1, file2),
+        EqualityMode::SizeAndMtime => {
+            // We have no reliable way to detect filesystem mtime granularity here, so compare
+            // at full (seconds, nanoseconds) resolution rather than assuming it's coarse;
+            // dropping the nanosecond component would only make false-equal verdicts more
+            // likely, not less.
+            let mt1 = truncated_mtime(&meta1)?;
+            let mt2 = truncated_mtime(&meta2)?;
 
-    loop {
-        match (f1.next(), f2.next()) {
-            // Both files have remaining bytes
-            (Some(Ok(b1)), Some(Ok(b2))) => {
-                if b1 != b2 {
-                    return Ok(false);
-                }
-            }
-            // One of the files is longer
-            (Some(Ok(_)), None) => {
-                return Ok(false);
-            }
-            (None, Some(Ok(_))) => {
-                return Ok(false);
-            }
-            // One of the iterators yields an error
-            (Some(Err(e)), _) => {
-                return Err(e);
-            }
-            (_, Some(Err(e))) => {
-                return Err(e);
+            if mt1.trustworthy && mt2.trustworthy && mt1.secs == mt2.secs && mt1.nanos == mt2.nanos {
+                return Ok(true);
             }
-            // Both iterators end at the same point
-            (None, None) => {
+
+            file_contents_equal(file1, file2)
+        }
+    }
+}
+
+/// Selects the I/O strategy [`file_contents_equal_with`] uses to compare file bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ComparisonStrategy {
+    /// Read both files into reusable fixed-size buffers and compare whole slices.
+    Buffered,
+    /// Memory-map both files and compare the mapped slices in one shot. Falls back to
+    /// [`ComparisonStrategy::Buffered`] if mapping fails. Two empty files are reported equal
+    /// without being mapped (mapping a zero-length file is an error on most platforms).
+    #[cfg(feature = "mmap")]
+    Mmap,
+}
+
+/// Size of the reusable buffers used by [`ComparisonStrategy::Buffered`].
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Byte-for-byte file comparison, dispatching on `strategy`.
+///
+/// The early length check is always performed first, since it's the cheapest possible way to
+/// rule out equality.
+pub fn file_contents_equal_with<P1: AsRef<Path>, P2: AsRef<Path>>(
+    file1: P1,
+    file2: P2,
+    strategy: ComparisonStrategy,
+) -> Result<bool, io::Error> {
+    let len1 = file1.as_ref().metadata()?.len();
+    let len2 = file2.as_ref().metadata()?.len();
+    if len1 != len2 {
+        return Ok(false);
+    }
+
+    match strategy {
+        ComparisonStrategy::Buffered => buffered_contents_equal(file1, file2),
+        #[cfg(feature = "mmap")]
+        ComparisonStrategy::Mmap => {
+            if len1 == 0 {
                 return Ok(true);
             }
+            match mmap_contents_equal(&file1, &file2) {
+                Ok(equal) => Ok(equal),
+                Err(_) => buffered_contents_equal(file1, file2),
+            }
         }
     }
 }
+
+fn buffered_contents_equal<P1: AsRef<Path>, P2: AsRef<Path>>(
+    file1: P1,
+    file2: P2,
+) -> Result<bool, io::Error> {
+    let mut f1 = File::open(file1)?;
+    let mut f2 = File::open(file2)?;
+
+    let mut buf1 = [0u8; BUFFER_SIZE];
+    let mut buf2 = [0u8; BUFFER_SIZE];
+
+    loop {
+        let n1 = read_fill(&mut f1, &mut buf1)?;
+        let n2 = read_fill(&mut f2, &mut buf2)?;
+
+        if n1 != n2 {
+            return Ok(false);
+        }
+        if n1 == 0 {
+            return Ok(true);
+        }
+        if buf1[..n1] != buf2[..n2] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Reads until `buf` is full or EOF is reached, returning the number of bytes read.
+///
+/// A plain `Read::read` call may return fewer bytes than requested without hitting EOF, so we
+/// can't compare partially-filled buffers directly without first topping them up.
+fn read_fill(f: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match f.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(feature = "mmap")]
+fn mmap_contents_equal<P1: AsRef<Path>, P2: AsRef<Path>>(
+    file1: P1,
+    file2: P2,
+) -> Result<bool, io::Error> {
+    let f1 = File::open(file1)?;
+    let f2 = File::open(file2)?;
+
+    // SAFETY: the mapped files are not expected to be modified by another process for the
+    // duration of the comparison. If either is truncated while mapped, accessing the pages
+    // past the new end raises SIGBUS rather than returning a torn read, so this is only safe
+    // to call against files we believe are not concurrently being written.
+    let m1 = unsafe { memmap2::Mmap::map(&f1)? };
+    let m2 = unsafe { memmap2::Mmap::map(&f2)? };
+
+    Ok(*m1 == *m2)
+}
+
+pub fn file_contents_equal<P1: AsRef<Path>, P2: AsRef<Path>>(
+    file1: P1,
+    file2: P2,
+) -> Result<bool, io::Error> {
+    file_contents_equal_with(file1, file2, ComparisonStrategy::Buffered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn truncated_mtime_is_untrustworthy_when_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fresh");
+        std::fs::write(&path, b"fresh").unwrap();
+
+        let mt = truncated_mtime(&path.metadata().unwrap()).unwrap();
+        assert!(!mt.trustworthy);
+    }
+
+    #[test]
+    fn truncated_mtime_is_trustworthy_once_aged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aged");
+        std::fs::write(&path, b"aged").unwrap();
+
+        let stale = SystemTime::now() - Duration::from_secs(2);
+        let f = File::open(&path).unwrap();
+        f.set_modified(stale).unwrap();
+
+        let mt = truncated_mtime(&path.metadata().unwrap()).unwrap();
+        assert!(mt.trustworthy);
+    }
+
+    #[test]
+    fn file_contents_equal_cached_size_only_ignores_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::write(&a, b"aaaa").unwrap();
+        std::fs::write(&b, b"bbbb").unwrap();
+
+        assert!(file_contents_equal_cached(&a, &b, EqualityMode::SizeOnly).unwrap());
+    }
+
+    #[test]
+    fn file_contents_equal_cached_size_and_mtime_falls_back_to_bytes_when_fresh() {
+        // Freshly-written files aren't trustworthy by mtime alone, so a same-size,
+        // different-content pair must still be caught by the byte-level fallback.
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::write(&a, b"aaaa").unwrap();
+        std::fs::write(&b, b"bbbb").unwrap();
+
+        assert!(!file_contents_equal_cached(&a, &b, EqualityMode::SizeAndMtime).unwrap());
+    }
+
+    #[test]
+    fn file_contents_equal_cached_byte_exact_detects_difference() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::write(&a, b"same").unwrap();
+        std::fs::write(&b, b"diff").unwrap();
+
+        assert!(!file_contents_equal_cached(&a, &b, EqualityMode::ByteExact).unwrap());
+    }
+
+    #[test]
+    fn read_fill_returns_actual_length_short_of_buf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("short");
+        std::fs::write(&path, b"short").unwrap();
+
+        let mut f = File::open(&path).unwrap();
+        let mut buf = [0u8; BUFFER_SIZE];
+        assert_eq!(read_fill(&mut f, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"short");
+    }
+
+    #[test]
+    fn buffered_contents_equal_across_multiple_buffer_fills() {
+        // Exercises the read_fill-driven loop across more than one BUFFER_SIZE-worth of bytes,
+        // rather than just the single-read case.
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let payload = vec![7u8; BUFFER_SIZE * 2 + 1];
+        std::fs::write(&a, &payload).unwrap();
+        std::fs::write(&b, &payload).unwrap();
+        assert!(buffered_contents_equal(&a, &b).unwrap());
+
+        let mut differs = payload.clone();
+        *differs.last_mut().unwrap() = 8;
+        std::fs::write(&b, &differs).unwrap();
+        assert!(!buffered_contents_equal(&a, &b).unwrap());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_strategy_treats_empty_files_as_equal_without_mapping() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::write(&a, b"").unwrap();
+        std::fs::write(&b, b"").unwrap();
+
+        assert!(
+            file_contents_equal_with(&a, &b, ComparisonStrategy::Mmap).unwrap()
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_contents_equal_errors_on_unmappable_file() {
+        // Mapping a directory's fd fails; callers (file_contents_equal_with) use this Err to
+        // fall back to the buffered comparison instead of propagating it.
+        let dir = tempfile::tempdir().unwrap();
+        assert!(mmap_contents_equal(dir.path(), dir.path()).is_err());
+    }
+}